@@ -8,15 +8,52 @@
 
 //! Multi-producer, single-consumer FIFO queue communication primitives.
 
-pub use std::sync::mpsc::{TrySendError, SendError, TryRecvError, RecvError};
+pub mod broadcast;
+pub mod oneshot;
 
+pub use std::sync::mpsc::{TrySendError, SendError, TryRecvError, RecvError, RecvTimeoutError};
+
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use coroutine::HandleList;
 use runtime::Processor;
 use scheduler::Scheduler;
 
+/// An error returned by `SyncSender::send_timeout`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+    /// The data could not be sent because the channel is full and the
+    /// timeout expired before a slot became free.
+    Timeout(T),
+    /// The data could not be sent because the receiving end has hung up.
+    Disconnected(T),
+}
+
+impl<T> fmt::Display for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SendTimeoutError::Timeout(..) => "timed out waiting on send operation".fmt(f),
+            SendTimeoutError::Disconnected(..) => "sending on a closed channel".fmt(f),
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for SendTimeoutError<T> {
+    fn description(&self) -> &str {
+        match *self {
+            SendTimeoutError::Timeout(..) => "timed out waiting on send operation",
+            SendTimeoutError::Disconnected(..) => "sending on a closed channel",
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Sender<T> {
     inner: Option<mpsc::Sender<T>>,
@@ -115,6 +152,102 @@ impl<T> Receiver<T> {
         // What? The processor is gone? Then fallback to blocking recv
         self.inner.recv()
     }
+
+    /// Like `recv`, but gives up once `dur` has elapsed.
+    ///
+    /// There is no runtime-level timer to register the coroutine with, so
+    /// unlike `recv` this cooperatively polls: each iteration retries
+    /// `try_recv` and, while still empty, yields to the scheduler with
+    /// `Scheduler::sched()` before checking the deadline again.
+    pub fn recv_timeout(&self, dur: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + dur;
+
+        while Processor::current().is_some() {
+            match self.try_recv() {
+                Ok(v) => return Ok(v),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+            }
+
+            if Instant::now() >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+
+            Scheduler::sched();
+        }
+
+        // What? The processor is gone? Then fallback to blocking recv_timeout
+        self.inner.recv_timeout(dur)
+    }
+
+    /// Returns an iterator that yields values via `recv`, parking the
+    /// coroutine while the channel is empty and stopping on disconnect.
+    pub fn iter(&self) -> Iter<T> {
+        Iter { rx: self }
+    }
+
+    /// Returns an iterator that yields values via `try_recv`, stopping as
+    /// soon as the channel is empty rather than parking the coroutine.
+    pub fn try_iter(&self) -> TryIter<T> {
+        TryIter { rx: self }
+    }
+}
+
+/// An iterator over messages received from a `Receiver`, created by `Receiver::iter`.
+pub struct Iter<'a, T: 'a> {
+    rx: &'a Receiver<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+/// An iterator over messages received from a `Receiver`, created by `Receiver::try_iter`.
+pub struct TryIter<'a, T: 'a> {
+    rx: &'a Receiver<T>,
+}
+
+impl<'a, T> Iterator for TryIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.try_recv().ok()
+    }
+}
+
+/// An owning iterator over messages received from a `Receiver`, created by `Receiver::into_iter`.
+pub struct IntoIter<T> {
+    rx: Receiver<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+impl<T> IntoIterator for Receiver<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { rx: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
 }
 
 /// Create a channel pair
@@ -141,14 +274,49 @@ pub struct SyncSender<T> {
 
     send_wait_list: Arc<Mutex<HandleList>>,
     recv_wait_list: Arc<Mutex<HandleList>>,
+
+    bound: usize,
+    reserved: Arc<AtomicUsize>,
+    disconnected: Arc<AtomicBool>,
+}
+
+/// The outcome of trying to reserve a slot in a bounded channel.
+enum Reservation {
+    Reserved,
+    Full,
+    Disconnected,
 }
 
 unsafe impl<T: Send> Send for SyncSender<T> {}
 
 impl<T> SyncSender<T> {
+    /// Releases a slot reserved via `try_reserve` that turned out not to be
+    /// used, e.g. because the underlying send it guarded failed.
+    fn release_reservation(&self) {
+        self.reserved.fetch_sub(1, Ordering::SeqCst);
+
+        let mut send_wait_list = self.send_wait_list.lock().unwrap();
+        if let Some(coro) = send_wait_list.pop_front() {
+            Scheduler::ready(coro);
+        }
+    }
+
     pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        // The `reserved` counter is the single source of truth for how many
+        // slots are spoken for (both in-flight `Permit`s and values already
+        // sitting in the inner channel), so route ordinary sends through the
+        // same accounting `reserve` uses -- otherwise a direct `try_send` can
+        // steal a slot a `Permit` already promised, or vice versa.
+        match self.try_reserve() {
+            Reservation::Full => return Err(TrySendError::Full(t)),
+            Reservation::Disconnected => return Err(TrySendError::Disconnected(t)),
+            Reservation::Reserved => {}
+        }
+
         match self.inner.as_ref().unwrap().try_send(t) {
             Ok(..) => {
+                // The slot stays reserved until `SyncReceiver` consumes the
+                // value; only then is the reservation released.
                 let mut recv_wait_list = self.recv_wait_list.lock().unwrap();
                 if let Some(coro) = recv_wait_list.pop_front() {
                     trace!("{:?} is waken up in SyncSender receive_wait_list, {} \
@@ -159,7 +327,14 @@ impl<T> SyncSender<T> {
                 }
                 Ok(())
             }
-            Err(err) => Err(err),
+            Err(err) => {
+                // With a bound above zero this only happens if the receiver
+                // hung up after `try_reserve` checked; with a zero bound it is
+                // how a send with no receiver waiting fails. Either way, don't
+                // leak the reservation.
+                self.release_reservation();
+                Err(err)
+            }
         }
     }
 
@@ -204,16 +379,191 @@ impl<T> SyncSender<T> {
             }
         }
 
-        match self.inner.as_ref().unwrap().send(t) {
+        // What? The processor is gone? Then fall back to a busy-poll through
+        // `try_send`, so the reservation accounting above still applies --
+        // calling the inner `SyncSender::send` directly here would bypass it.
+        loop {
+            match self.try_send(t) {
+                Ok(..) => return Ok(()),
+                Err(TrySendError::Disconnected(e)) => return Err(SendError(e)),
+                Err(TrySendError::Full(t_)) => {
+                    t = t_;
+                    thread::yield_now();
+                }
+            }
+        }
+    }
+
+    /// Like `send`, but gives up once `dur` has elapsed.
+    ///
+    /// There is no runtime-level timer to register the coroutine with, so
+    /// unlike `send` this cooperatively polls: each iteration retries
+    /// `try_send` and, while still full, yields to the scheduler with
+    /// `Scheduler::sched()` before checking the deadline again.
+    pub fn send_timeout(&self, t: T, dur: Duration) -> Result<(), SendTimeoutError<T>> {
+        let deadline = Instant::now() + dur;
+        let mut slot = Some(t);
+
+        while Processor::current().is_some() {
+            let t = slot.take().unwrap();
+            match self.try_send(t) {
+                Ok(..) => return Ok(()),
+                Err(TrySendError::Disconnected(e)) => return Err(SendTimeoutError::Disconnected(e)),
+                Err(TrySendError::Full(t)) => slot = Some(t),
+            }
+
+            if Instant::now() >= deadline {
+                return Err(SendTimeoutError::Timeout(slot.take().unwrap()));
+            }
+
+            Scheduler::sched();
+        }
+
+        // What? The processor is gone? Then fallback to a busy-poll, since
+        // libstd's `mpsc::SyncSender` has no timed send of its own.
+        loop {
+            let t = slot.take().unwrap();
+            match self.try_send(t) {
+                Ok(..) => return Ok(()),
+                Err(TrySendError::Disconnected(e)) => return Err(SendTimeoutError::Disconnected(e)),
+                Err(TrySendError::Full(t)) => slot = Some(t),
+            }
+
+            if Instant::now() >= deadline {
+                return Err(SendTimeoutError::Timeout(slot.take().unwrap()));
+            }
+
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn try_reserve(&self) -> Reservation {
+        if self.disconnected.load(Ordering::SeqCst) {
+            return Reservation::Disconnected;
+        }
+
+        loop {
+            let current = self.reserved.load(Ordering::SeqCst);
+
+            // A zero-capacity channel is a rendezvous: there is no slot to
+            // count, so `reserved` only tracks in-flight attempts for
+            // bookkeeping symmetry with `release_reservation`, and whether a
+            // send actually succeeds is entirely up to the inner channel's
+            // own rendezvous logic.
+            if self.bound > 0 && current >= self.bound {
+                return Reservation::Full;
+            }
+
+            match self.reserved.compare_exchange(current, current + 1, Ordering::SeqCst, Ordering::SeqCst) {
+                Ok(..) => return Reservation::Reserved,
+                Err(..) => {}
+            }
+        }
+    }
+
+    /// Cooperatively parks until a slot is free, then returns a `Permit`
+    /// that guarantees a subsequent `Permit::send` cannot fail with `Full`.
+    ///
+    /// A zero-capacity channel has no slot to hold: there `reserve` returns
+    /// at once, and `Permit::send` fails with `SendError` unless the receiver
+    /// is already blocked in `recv`.
+    pub fn reserve(&self) -> Result<Permit<T>, SendError<()>> {
+        while let Some(p) = Processor::current() {
+            match self.try_reserve() {
+                Reservation::Reserved => return Ok(Permit { sender: self, transferred: Cell::new(false) }),
+                Reservation::Disconnected => return Err(SendError(())),
+                Reservation::Full => {}
+            }
+
+            let mut reservation = Reservation::Full;
+            p.park_with(|p, coro| {
+                let mut send_wait_list = self.send_wait_list.lock().unwrap();
+
+                reservation = self.try_reserve();
+
+                match reservation {
+                    Reservation::Full => {
+                        send_wait_list.push_back(coro);
+                    }
+                    _ => {
+                        p.ready(coro);
+                    }
+                }
+            });
+
+            match reservation {
+                Reservation::Reserved => return Ok(Permit { sender: self, transferred: Cell::new(false) }),
+                Reservation::Disconnected => return Err(SendError(())),
+                Reservation::Full => {}
+            }
+        }
+
+        // What? The processor is gone? Then fall back to a busy-poll.
+        loop {
+            match self.try_reserve() {
+                Reservation::Reserved => return Ok(Permit { sender: self, transferred: Cell::new(false) }),
+                Reservation::Disconnected => return Err(SendError(())),
+                Reservation::Full => thread::yield_now(),
+            }
+        }
+    }
+}
+
+/// A reserved slot in a bounded channel, obtained via `SyncSender::reserve`.
+///
+/// Dropping a `Permit` without calling `Permit::send` returns the reserved
+/// slot to the channel's capacity. After a successful `Permit::send` the slot
+/// stays taken until `SyncReceiver` consumes the value.
+pub struct Permit<'a, T: 'a> {
+    sender: &'a SyncSender<T>,
+
+    // Set once the reserved slot has actually been filled with a value, so
+    // `Drop` knows not to release a reservation that is now occupied by data
+    // sitting in the channel -- that slot is only released once
+    // `SyncReceiver` consumes it (see `SyncReceiver::try_recv`).
+    transferred: Cell<bool>,
+}
+
+impl<'a, T> Permit<'a, T> {
+    /// Completes the send using the slot reserved by `SyncSender::reserve`.
+    ///
+    /// Unlike `SyncSender::send`, this cannot block or return `Full`. On a
+    /// zero-capacity channel it returns `SendError` when no receiver is
+    /// blocked in `recv` to take the value.
+    pub fn send(self, t: T) -> Result<(), SendError<T>> {
+        match self.sender.inner.as_ref().unwrap().try_send(t) {
             Ok(..) => {
-                let mut recv_wait_list = self.recv_wait_list.lock().unwrap();
+                let mut recv_wait_list = self.sender.recv_wait_list.lock().unwrap();
                 if let Some(coro) = recv_wait_list.pop_front() {
-                    // Wake them up ...
                     Scheduler::ready(coro);
                 }
+                self.transferred.set(true);
                 Ok(())
             }
-            Err(err) => Err(err),
+            Err(TrySendError::Disconnected(t)) => Err(SendError(t)),
+            Err(TrySendError::Full(t)) => {
+                // The reservation guarantees room when the bound is above
+                // zero; a zero-capacity channel reaches this when no receiver
+                // is waiting.
+                Err(SendError(t))
+            }
+        }
+        // If the send failed, `self` still drops here and `Drop` releases
+        // the now-unused reservation.
+    }
+}
+
+impl<'a, T> Drop for Permit<'a, T> {
+    fn drop(&mut self) {
+        if self.transferred.get() {
+            return;
+        }
+
+        self.sender.reserved.fetch_sub(1, Ordering::SeqCst);
+
+        let mut send_wait_list = self.sender.send_wait_list.lock().unwrap();
+        if let Some(coro) = send_wait_list.pop_front() {
+            Scheduler::ready(coro);
         }
     }
 }
@@ -245,6 +595,9 @@ pub struct SyncReceiver<T> {
 
     send_wait_list: Arc<Mutex<HandleList>>,
     recv_wait_list: Arc<Mutex<HandleList>>,
+
+    disconnected: Arc<AtomicBool>,
+    reserved: Arc<AtomicUsize>,
 }
 
 unsafe impl<T: Send> Send for SyncReceiver<T> {}
@@ -253,6 +606,11 @@ impl<T> SyncReceiver<T> {
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
         match self.inner.as_ref().unwrap().try_recv() {
             Ok(t) => {
+                // The slot this value occupied (reserved either by a plain
+                // `send`/`try_send` or by a `Permit`) is only released now
+                // that it has actually been consumed.
+                self.reserved.fetch_sub(1, Ordering::SeqCst);
+
                 let mut send_wait_list = self.send_wait_list.lock().unwrap();
                 if let Some(coro) = send_wait_list.pop_front() {
                     trace!("{:?} is waken up in SyncReceiver send_wait_list, {} remains",
@@ -301,6 +659,8 @@ impl<T> SyncReceiver<T> {
         // What? The processor is gone? Then use blocking recv
         match self.inner.as_ref().unwrap().recv() {
             Ok(t) => {
+                self.reserved.fetch_sub(1, Ordering::SeqCst);
+
                 let mut send_wait_list = self.send_wait_list.lock().unwrap();
                 if let Some(coro) = send_wait_list.pop_front() {
                     Scheduler::ready(coro);
@@ -310,6 +670,113 @@ impl<T> SyncReceiver<T> {
             Err(err) => Err(err),
         }
     }
+
+    /// Like `recv`, but gives up once `dur` has elapsed.
+    ///
+    /// There is no runtime-level timer to register the coroutine with, so
+    /// unlike `recv` this cooperatively polls: each iteration retries
+    /// `try_recv` and, while still empty, yields to the scheduler with
+    /// `Scheduler::sched()` before checking the deadline again.
+    pub fn recv_timeout(&self, dur: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + dur;
+
+        while Processor::current().is_some() {
+            match self.try_recv() {
+                Ok(v) => return Ok(v),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => return Err(RecvTimeoutError::Disconnected),
+            }
+
+            if Instant::now() >= deadline {
+                return Err(RecvTimeoutError::Timeout);
+            }
+
+            Scheduler::sched();
+        }
+
+        // What? The processor is gone? Then use blocking recv_timeout
+        match self.inner.as_ref().unwrap().recv_timeout(dur) {
+            Ok(t) => {
+                self.reserved.fetch_sub(1, Ordering::SeqCst);
+
+                let mut send_wait_list = self.send_wait_list.lock().unwrap();
+                if let Some(coro) = send_wait_list.pop_front() {
+                    Scheduler::ready(coro);
+                }
+                Ok(t)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns an iterator that yields values via `recv`, parking the
+    /// coroutine while the channel is empty and stopping on disconnect.
+    pub fn iter(&self) -> SyncIter<T> {
+        SyncIter { rx: self }
+    }
+
+    /// Returns an iterator that yields values via `try_recv`, stopping as
+    /// soon as the channel is empty rather than parking the coroutine.
+    pub fn try_iter(&self) -> SyncTryIter<T> {
+        SyncTryIter { rx: self }
+    }
+}
+
+/// An iterator over messages received from a `SyncReceiver`, created by `SyncReceiver::iter`.
+pub struct SyncIter<'a, T: 'a> {
+    rx: &'a SyncReceiver<T>,
+}
+
+impl<'a, T> Iterator for SyncIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+/// An iterator over messages received from a `SyncReceiver`, created by `SyncReceiver::try_iter`.
+pub struct SyncTryIter<'a, T: 'a> {
+    rx: &'a SyncReceiver<T>,
+}
+
+impl<'a, T> Iterator for SyncTryIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.try_recv().ok()
+    }
+}
+
+/// An owning iterator over messages received from a `SyncReceiver`, created by `SyncReceiver::into_iter`.
+pub struct SyncIntoIter<T> {
+    rx: SyncReceiver<T>,
+}
+
+impl<T> Iterator for SyncIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+impl<T> IntoIterator for SyncReceiver<T> {
+    type Item = T;
+    type IntoIter = SyncIntoIter<T>;
+
+    fn into_iter(self) -> SyncIntoIter<T> {
+        SyncIntoIter { rx: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SyncReceiver<T> {
+    type Item = T;
+    type IntoIter = SyncIter<'a, T>;
+
+    fn into_iter(self) -> SyncIter<'a, T> {
+        self.iter()
+    }
 }
 
 impl<T> Drop for SyncReceiver<T> {
@@ -319,6 +786,10 @@ impl<T> Drop for SyncReceiver<T> {
             self.inner.take();
         }
 
+        // This is also the only place a receiver goes away, so flag the
+        // channel as disconnected before waking anyone blocked in `reserve`.
+        self.disconnected.store(true, Ordering::SeqCst);
+
         // Try to wake up all the pending coroutines if this is the last SyncReceiver.
         // Because there won't be another one to push items into this queue, so we
         // have to wake the coroutine up explicitly, who ownes the other end of this channel.
@@ -341,17 +812,24 @@ pub fn sync_channel<T>(bound: usize) -> (SyncSender<T>, SyncReceiver<T>) {
     let (tx, rx) = mpsc::sync_channel(bound);
     let send_wait_list = Arc::new(Mutex::new(HandleList::new()));
     let recv_wait_list = Arc::new(Mutex::new(HandleList::new()));
+    let disconnected = Arc::new(AtomicBool::new(false));
+    let reserved = Arc::new(AtomicUsize::new(0));
 
     let sender = SyncSender {
         inner: Some(tx),
         send_wait_list: send_wait_list.clone(),
         recv_wait_list: recv_wait_list.clone(),
+        bound: bound,
+        reserved: reserved.clone(),
+        disconnected: disconnected.clone(),
     };
 
     let receiver = SyncReceiver {
         inner: Some(rx),
         send_wait_list: send_wait_list,
         recv_wait_list: recv_wait_list,
+        disconnected: disconnected,
+        reserved: reserved,
     };
 
     (sender, receiver)
@@ -428,6 +906,26 @@ mod test {
             .unwrap();
     }
 
+    #[test]
+    fn test_sync_channel_rendezvous() {
+        // A zero-capacity channel has no slot to reserve; `try_send` must
+        // fail with `Full` until a receiver is actually waiting to
+        // rendezvous, not forever.
+        let (tx, rx) = sync_channel(0);
+
+        assert_eq!(tx.try_send(1), Err(TrySendError::Full(1)));
+
+        let h = thread::spawn(move || {
+            assert_eq!(rx.recv(), Ok(1));
+        });
+
+        // Give the receiver a moment to actually park in the blocking recv.
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(tx.send(1), Ok(()));
+
+        h.join().unwrap();
+    }
+
     #[test]
     fn test_channel_without_processor() {
         let (tx1, rx1) = channel();
@@ -564,4 +1062,198 @@ mod test {
             })
             .unwrap();
     }
+
+    #[test]
+    fn test_channel_recv_timeout() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = channel();
+
+                assert_eq!(rx.recv_timeout(Duration::from_millis(10)),
+                           Err(RecvTimeoutError::Timeout));
+
+                assert_eq!(tx.send(1), Ok(()));
+                assert_eq!(rx.recv_timeout(Duration::from_millis(100)), Ok(1));
+
+                drop(tx);
+                assert_eq!(rx.recv_timeout(Duration::from_millis(100)),
+                           Err(RecvTimeoutError::Disconnected));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sync_channel_send_timeout() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = sync_channel(1);
+
+                assert_eq!(tx.send_timeout(1, Duration::from_millis(100)), Ok(()));
+                match tx.send_timeout(2, Duration::from_millis(10)) {
+                    Err(SendTimeoutError::Timeout(2)) => {}
+                    other => panic!("expected SendTimeoutError::Timeout(2), got {:?}", other),
+                }
+
+                assert_eq!(rx.recv(), Ok(1));
+                assert_eq!(tx.send_timeout(2, Duration::from_millis(100)), Ok(()));
+                assert_eq!(rx.recv(), Ok(2));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sync_channel_reserve() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = sync_channel(1);
+
+                let permit = tx.reserve().unwrap();
+                assert_eq!(permit.send(1), Ok(()));
+
+                assert_eq!(rx.recv(), Ok(1));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sync_channel_reserve_returns_capacity_on_drop() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = sync_channel(1);
+
+                {
+                    let permit = tx.reserve().unwrap();
+                    drop(permit);
+                }
+
+                // The dropped permit must have given the slot back.
+                assert_eq!(tx.try_send(1), Ok(()));
+                assert_eq!(rx.recv(), Ok(1));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sync_channel_reserve_disconnected() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = sync_channel::<i32>(1);
+                drop(rx);
+
+                match tx.reserve() {
+                    Err(SendError(())) => {}
+                    Ok(..) => panic!("expected reserve() to observe the disconnect"),
+                };
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sync_channel_send_fills_reserve_accounting() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = sync_channel(1);
+
+                // A plain `send` must count against the same `reserved`
+                // budget as `reserve`, otherwise a `reserve` call would think
+                // the slot a direct send just filled is still free.
+                assert_eq!(tx.try_send(1), Ok(()));
+                assert_eq!(tx.try_send(2), Err(TrySendError::Full(2)));
+
+                let h = Scheduler::spawn(move || {
+                    // Must park until `rx.recv()` below frees the slot.
+                    let permit = tx.reserve().unwrap();
+                    assert_eq!(permit.send(2), Ok(()));
+                });
+
+                Scheduler::sched();
+
+                assert_eq!(rx.recv(), Ok(1));
+                assert_eq!(rx.recv(), Ok(2));
+
+                h.join().unwrap();
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sync_channel_reserve_blocks_concurrent_send() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = sync_channel(1);
+
+                // Hold the only slot with a `Permit` that hasn't sent yet.
+                let permit = tx.reserve().unwrap();
+
+                // A direct send must not be able to steal the reserved slot.
+                assert_eq!(tx.try_send(1), Err(TrySendError::Full(1)));
+
+                assert_eq!(permit.send(2), Ok(()));
+                assert_eq!(rx.recv(), Ok(2));
+
+                // Now that the `Permit`'s value has been consumed, the slot
+                // is free again.
+                assert_eq!(tx.try_send(3), Ok(()));
+                assert_eq!(rx.recv(), Ok(3));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_channel_iter() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = channel();
+
+                let h = Scheduler::spawn(move || {
+                    let received: Vec<_> = rx.iter().collect();
+                    assert_eq!(received, vec![1, 2, 3]);
+                });
+
+                for i in 1..4 {
+                    tx.send(i).unwrap();
+                }
+                drop(tx);
+
+                h.join().unwrap();
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_channel_try_iter() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = channel();
+
+                tx.send(1).unwrap();
+                tx.send(2).unwrap();
+
+                let received: Vec<_> = rx.try_iter().collect();
+                assert_eq!(received, vec![1, 2]);
+                assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sync_channel_into_iter() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = sync_channel(2);
+
+                let h = Scheduler::spawn(move || {
+                    let received: Vec<_> = rx.into_iter().collect();
+                    assert_eq!(received, vec![1, 2, 3]);
+                });
+
+                for i in 1..4 {
+                    tx.send(i).unwrap();
+                }
+                drop(tx);
+
+                h.join().unwrap();
+            })
+            .unwrap();
+    }
 }
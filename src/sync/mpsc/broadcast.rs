@@ -0,0 +1,417 @@
+// Copyright 2015 The coio Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Multi-producer, multi-consumer broadcast communication primitive.
+//!
+//! Every value sent by a `Sender` is observed by every `Receiver` that was
+//! subscribed before it was sent, mirroring the semantics of
+//! `tokio::sync::broadcast`.
+
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use coroutine::HandleList;
+use runtime::Processor;
+use scheduler::Scheduler;
+
+struct Slot<T> {
+    seq: u64,
+    value: Option<T>,
+}
+
+struct Shared<T> {
+    slots: Vec<Slot<T>>,
+    tail: u64,
+    sender_count: usize,
+    receiver_count: usize,
+    wait_list: HandleList,
+}
+
+/// The sending half of a broadcast channel.
+pub struct Sender<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    capacity: usize,
+}
+
+unsafe impl<T: Send> Send for Sender<T> {}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Sender<T> {
+        self.shared.lock().unwrap().sender_count += 1;
+
+        Sender {
+            shared: self.shared.clone(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+impl<T: Clone> Sender<T> {
+    /// Sends a value to all subscribed receivers.
+    ///
+    /// Returns `Err` with the value back if there are no receivers left to
+    /// observe it.
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let mut shared = self.shared.lock().unwrap();
+
+        if shared.receiver_count == 0 {
+            return Err(SendError(t));
+        }
+
+        let tail = shared.tail;
+        let idx = (tail % self.capacity as u64) as usize;
+        shared.slots[idx] = Slot {
+            seq: tail,
+            value: Some(t),
+        };
+        shared.tail += 1;
+
+        while let Some(coro) = shared.wait_list.pop_front() {
+            Scheduler::ready(coro);
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new `Receiver` that will only observe values sent after
+    /// this call.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut shared = self.shared.lock().unwrap();
+        shared.receiver_count += 1;
+
+        Receiver {
+            shared: self.shared.clone(),
+            capacity: self.capacity,
+            next_seq: Cell::new(shared.tail),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.sender_count -= 1;
+
+        // Try to wake up all the pending coroutines if this is the last Sender.
+        // Because if this is the last Sender, there won't be another one to push
+        // items into this queue, so we have to wake the coroutine up explicitly,
+        // who ownes the other end of this channel.
+        if shared.sender_count == 0 {
+            while let Some(hdl) = shared.wait_list.pop_front() {
+                trace!("{:?} is awaken by dropping Sender in wait_list", hdl);
+                Scheduler::ready(hdl);
+            }
+        }
+    }
+}
+
+/// The receiving half of a broadcast channel.
+pub struct Receiver<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    capacity: usize,
+    next_seq: Cell<u64>,
+}
+
+unsafe impl<T: Send> Send for Receiver<T> {}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.receiver_count -= 1;
+    }
+}
+
+impl<T: Clone> Receiver<T> {
+    fn poll(&self, shared: &mut Shared<T>) -> Result<T, TryRecvError> {
+        let next_seq = self.next_seq.get();
+        let lag = shared.tail.saturating_sub(next_seq);
+
+        if lag == 0 {
+            return if shared.sender_count == 0 {
+                Err(TryRecvError::Closed)
+            } else {
+                Err(TryRecvError::Empty)
+            };
+        }
+
+        if lag > self.capacity as u64 {
+            let skipped = lag - self.capacity as u64;
+            self.next_seq.set(shared.tail - self.capacity as u64);
+            return Err(TryRecvError::Lagged(skipped));
+        }
+
+        let idx = (next_seq % self.capacity as u64) as usize;
+        let slot = &shared.slots[idx];
+
+        // The lag check above should already guarantee `next_seq` is still
+        // within the window, but double-check against the slot's own stamp
+        // in case a future change to the lag math lets a stale slot through.
+        debug_assert_eq!(slot.seq, next_seq, "read a stale slot out of the ring buffer");
+
+        let value = slot.value.clone().expect("slot within the receiver's window must be filled");
+        self.next_seq.set(next_seq + 1);
+
+        Ok(value)
+    }
+
+    /// Attempts to receive a value without parking the coroutine.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut shared = self.shared.lock().unwrap();
+        self.poll(&mut shared)
+    }
+
+    /// Receives the next value, parking the coroutine until one is sent.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        while let Some(processor) = Processor::current() {
+            // 1. Try to receive first
+            let mut r = self.try_recv();
+            match r {
+                Err(TryRecvError::Empty) => {}
+                _ => return r.map_err(RecvError::from_try_recv_error),
+            }
+
+            // 2. Yield
+            processor.park_with(|p, coro| {
+                // 3. Lock the shared state
+                let mut shared = self.shared.lock().unwrap();
+
+                // 4. Try to receive again, to ensure no one sent items while
+                //    we are locking the shared state
+                r = self.poll(&mut shared);
+
+                match r {
+                    Err(TryRecvError::Empty) => {
+                        // 5.1. Push ourselves into the wait list
+                        shared.wait_list.push_back(coro);
+                    }
+                    _ => {
+                        // 5.2. Success!
+                        p.ready(coro);
+                    }
+                }
+            });
+
+            // 6. Check it again after being waken up (if 5.2 succeeded)
+            match r {
+                Err(TryRecvError::Empty) => {}
+                _ => return r.map_err(RecvError::from_try_recv_error),
+            }
+        }
+
+        // What? The processor is gone? Then fall back to a busy-wait, there
+        // is no OS-level blocking primitive backing this ring buffer.
+        loop {
+            match self.try_recv() {
+                Err(TryRecvError::Empty) => {}
+                r => return r.map_err(RecvError::from_try_recv_error),
+            }
+        }
+    }
+}
+
+/// An error returned by `Sender::send`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        "sending on a closed channel".fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> Error for SendError<T> {
+    fn description(&self) -> &str {
+        "sending on a closed channel"
+    }
+}
+
+/// An error returned by `Receiver::try_recv`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TryRecvError {
+    /// No value has been sent since the last one this receiver observed.
+    Empty,
+    /// All `Sender`s have been dropped.
+    Closed,
+    /// The receiver missed `n` values because it fell behind the ring buffer.
+    Lagged(u64),
+}
+
+/// An error returned by `Receiver::recv`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RecvError {
+    /// All `Sender`s have been dropped.
+    Closed,
+    /// The receiver missed `n` values because it fell behind the ring buffer.
+    Lagged(u64),
+}
+
+impl RecvError {
+    fn from_try_recv_error(err: TryRecvError) -> RecvError {
+        match err {
+            TryRecvError::Empty => unreachable!("Empty must be retried, not converted"),
+            TryRecvError::Closed => RecvError::Closed,
+            TryRecvError::Lagged(n) => RecvError::Lagged(n),
+        }
+    }
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecvError::Closed => "receiving on a closed channel".fmt(f),
+            RecvError::Lagged(n) => write!(f, "channel lagged by {} messages", n),
+        }
+    }
+}
+
+impl Error for RecvError {
+    fn description(&self) -> &str {
+        match *self {
+            RecvError::Closed => "receiving on a closed channel",
+            RecvError::Lagged(..) => "receiver lagged behind the channel",
+        }
+    }
+}
+
+/// Creates a new broadcast channel, returning the `Sender`/`Receiver` halves.
+///
+/// `capacity` is the number of values the ring buffer retains; a `Receiver`
+/// that falls more than `capacity` values behind will observe
+/// `RecvError::Lagged` and skip ahead to the oldest value still buffered.
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "broadcast channel capacity must be greater than zero");
+
+    let slots = (0..capacity).map(|_| Slot { seq: 0, value: None }).collect();
+
+    let shared = Arc::new(Mutex::new(Shared {
+        slots: slots,
+        tail: 0,
+        sender_count: 1,
+        receiver_count: 1,
+        wait_list: HandleList::new(),
+    }));
+
+    let sender = Sender {
+        shared: shared.clone(),
+        capacity: capacity,
+    };
+
+    let receiver = Receiver {
+        shared: shared,
+        capacity: capacity,
+        next_seq: Cell::new(0),
+    };
+
+    (sender, receiver)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use scheduler::Scheduler;
+
+    #[test]
+    fn test_broadcast_basic() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx1) = channel(4);
+                let rx2 = tx.subscribe();
+
+                let h1 = Scheduler::spawn(move || {
+                    for i in 1..5 {
+                        assert_eq!(rx1.recv(), Ok(i));
+                    }
+                    assert_eq!(rx1.recv(), Err(RecvError::Closed));
+                });
+
+                let h2 = Scheduler::spawn(move || {
+                    for i in 1..5 {
+                        assert_eq!(rx2.recv(), Ok(i));
+                    }
+                    assert_eq!(rx2.recv(), Err(RecvError::Closed));
+                });
+
+                for i in 1..5 {
+                    assert_eq!(tx.send(i), Ok(()));
+                }
+
+                drop(tx);
+
+                h1.join().unwrap();
+                h2.join().unwrap();
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_broadcast_subscribe_only_sees_future_values() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx1) = channel(4);
+                assert_eq!(tx.send(1), Ok(()));
+
+                let rx2 = tx.subscribe();
+                assert_eq!(tx.send(2), Ok(()));
+
+                assert_eq!(rx1.try_recv(), Ok(1));
+                assert_eq!(rx1.try_recv(), Ok(2));
+
+                assert_eq!(rx2.try_recv(), Ok(2));
+                assert_eq!(rx2.try_recv(), Err(TryRecvError::Empty));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_broadcast_lagged() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = channel(2);
+
+                for i in 1..5 {
+                    assert_eq!(tx.send(i), Ok(()));
+                }
+
+                assert_eq!(rx.try_recv(), Err(TryRecvError::Lagged(2)));
+                assert_eq!(rx.try_recv(), Ok(3));
+                assert_eq!(rx.try_recv(), Ok(4));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_broadcast_send_with_no_receivers() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = channel(2);
+                drop(rx);
+
+                // With no receivers left to observe it, `send` must report
+                // the value back instead of silently discarding it.
+                assert_eq!(tx.send(1), Err(SendError(1)));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_broadcast_send_ok_while_any_receiver_remains() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx1) = channel(2);
+                let rx2 = tx.subscribe();
+                drop(rx1);
+
+                // `rx2` is still subscribed, so the send must still succeed.
+                assert_eq!(tx.send(1), Ok(()));
+                assert_eq!(rx2.try_recv(), Ok(1));
+            })
+            .unwrap();
+    }
+}
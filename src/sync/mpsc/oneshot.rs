@@ -0,0 +1,266 @@
+// Copyright 2015 The coio Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A coroutine-aware channel for sending a single value between two
+//! coroutines, as in futures-channel's `oneshot`.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use coroutine::HandleList;
+use runtime::Processor;
+use scheduler::Scheduler;
+
+struct Inner<T> {
+    value: Option<T>,
+    consumed: bool,
+    sender_alive: bool,
+    receiver_alive: bool,
+    wait_list: HandleList,
+}
+
+/// The sending half of a oneshot channel.
+pub struct Sender<T> {
+    inner: Arc<Mutex<Inner<T>>>,
+}
+
+unsafe impl<T: Send> Send for Sender<T> {}
+
+impl<T> Sender<T> {
+    /// Sends a single value to the paired `Receiver`.
+    ///
+    /// Returns the value back if the `Receiver` has already been dropped.
+    pub fn send(self, t: T) -> Result<(), T> {
+        let mut inner = self.inner.lock().unwrap();
+
+        if !inner.receiver_alive {
+            return Err(t);
+        }
+
+        inner.value = Some(t);
+        while let Some(coro) = inner.wait_list.pop_front() {
+            Scheduler::ready(coro);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.sender_alive = false;
+
+        // If a value was already sent there is nothing left to wake up for;
+        // the wait list was already drained in `send`.
+        if inner.value.is_none() {
+            while let Some(coro) = inner.wait_list.pop_front() {
+                trace!("{:?} is awaken by dropping Sender in wait_list", coro);
+                Scheduler::ready(coro);
+            }
+        }
+    }
+}
+
+/// The receiving half of a oneshot channel.
+pub struct Receiver<T> {
+    inner: Arc<Mutex<Inner<T>>>,
+}
+
+unsafe impl<T: Send> Send for Receiver<T> {}
+
+impl<T> Receiver<T> {
+    /// Checks for the value without parking the coroutine.
+    ///
+    /// Returns `Ok(None)` if the `Sender` is still alive and hasn't sent a
+    /// value yet. Once the value has been returned by a successful call,
+    /// every later call returns `Ok(None)` rather than re-reporting
+    /// `Canceled` for a value that was already delivered.
+    pub fn try_recv(&self) -> Result<Option<T>, Canceled> {
+        let mut inner = self.inner.lock().unwrap();
+
+        match inner.value.take() {
+            Some(v) => {
+                inner.consumed = true;
+                Ok(Some(v))
+            }
+            None => {
+                if inner.sender_alive || inner.consumed {
+                    Ok(None)
+                } else {
+                    Err(Canceled)
+                }
+            }
+        }
+    }
+
+    /// Parks the coroutine until the `Sender` sends a value or is dropped.
+    pub fn recv(self) -> Result<T, Canceled> {
+        while let Some(processor) = Processor::current() {
+            // 1. Try to receive first
+            match self.try_recv() {
+                Ok(Some(v)) => return Ok(v),
+                Ok(None) => {}
+                Err(Canceled) => return Err(Canceled),
+            }
+
+            // 2. Yield
+            processor.park_with(|p, coro| {
+                // 3. Lock the shared slot
+                let mut inner = self.inner.lock().unwrap();
+
+                // 4. Recheck under the lock, to ensure no one sent/dropped while
+                //    we were about to park
+                if inner.value.is_some() || !inner.sender_alive {
+                    p.ready(coro);
+                } else {
+                    inner.wait_list.push_back(coro);
+                }
+            });
+
+            // 5. Check it again after being waken up
+            match self.try_recv() {
+                Ok(Some(v)) => return Ok(v),
+                Ok(None) => {}
+                Err(Canceled) => return Err(Canceled),
+            }
+        }
+
+        // What? The processor is gone? There is no OS-level blocking primitive
+        // backing a single slot, so fall back to a busy-wait.
+        loop {
+            match self.try_recv() {
+                Ok(Some(v)) => return Ok(v),
+                Ok(None) => thread::yield_now(),
+                Err(Canceled) => return Err(Canceled),
+            }
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.inner.lock().unwrap().receiver_alive = false;
+    }
+}
+
+/// The error returned when the `Sender` is dropped without sending a value.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Canceled;
+
+impl fmt::Display for Canceled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        "oneshot canceled".fmt(f)
+    }
+}
+
+impl Error for Canceled {
+    fn description(&self) -> &str {
+        "oneshot canceled"
+    }
+}
+
+/// Creates a new oneshot channel, returning the `Sender`/`Receiver` halves.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Mutex::new(Inner {
+        value: None,
+        consumed: false,
+        sender_alive: true,
+        receiver_alive: true,
+        wait_list: HandleList::new(),
+    }));
+
+    let sender = Sender { inner: inner.clone() };
+    let receiver = Receiver { inner: inner };
+
+    (sender, receiver)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use scheduler::Scheduler;
+
+    #[test]
+    fn test_oneshot_basic() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = channel();
+
+                let h = Scheduler::spawn(move || {
+                    assert_eq!(rx.recv(), Ok(42));
+                });
+
+                assert_eq!(tx.send(42), Ok(()));
+
+                h.join().unwrap();
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_oneshot_canceled_on_sender_drop() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = channel::<i32>();
+
+                let h = Scheduler::spawn(move || {
+                    assert_eq!(rx.recv(), Err(Canceled));
+                });
+
+                drop(tx);
+
+                h.join().unwrap();
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_oneshot_send_after_receiver_dropped() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = channel();
+                drop(rx);
+
+                assert_eq!(tx.send(1), Err(1));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_oneshot_try_recv() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = channel();
+
+                assert_eq!(rx.try_recv(), Ok(None));
+                assert_eq!(tx.send(1), Ok(()));
+                assert_eq!(rx.try_recv(), Ok(Some(1)));
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_oneshot_try_recv_after_consuming_does_not_report_canceled() {
+        Scheduler::new()
+            .run(move || {
+                let (tx, rx) = channel();
+
+                assert_eq!(tx.send(1), Ok(()));
+                assert_eq!(rx.try_recv(), Ok(Some(1)));
+
+                // `send` consumed the sender, so it is gone by now; with its
+                // value already delivered, a later poll must not turn into a
+                // spurious `Canceled`.
+                assert_eq!(rx.try_recv(), Ok(None));
+            })
+            .unwrap();
+    }
+}